@@ -0,0 +1,62 @@
+//! パース済みタスクのキャッシュ
+//!
+//! `get_tasks` はフォルダ内の全`.md`を毎回読み直していたため、
+//! ファイルの更新日時（mtime）をキーにパース結果を再利用し、
+//! 変更があったファイルだけ `parse_markdown` を呼び直す。
+
+use crate::parser::Task;
+use moka::sync::Cache;
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+/// キャッシュの生存時間。mtime比較が効かない想定外のケースに対する安全網
+const CACHE_TTL_SECS: u64 = 300;
+
+/// ファイルパスをキーに (mtime, パース済みTask) を保持するキャッシュ
+pub struct TaskCache {
+    inner: Cache<String, (SystemTime, Task)>,
+}
+
+impl TaskCache {
+    pub fn new() -> Self {
+        Self {
+            inner: Cache::builder()
+                .time_to_live(Duration::from_secs(CACHE_TTL_SECS))
+                .build(),
+        }
+    }
+
+    /// mtimeが一致すればキャッシュ済みのTaskを返す
+    pub fn get_if_fresh(&self, file_path: &str, mtime: SystemTime) -> Option<Task> {
+        self.inner
+            .get(file_path)
+            .and_then(|(cached_mtime, task)| (cached_mtime == mtime).then_some(task))
+    }
+
+    pub fn insert(&self, file_path: String, mtime: SystemTime, task: Task) {
+        self.inner.insert(file_path, (mtime, task));
+    }
+
+    /// 単一ファイルのキャッシュを無効化
+    pub fn invalidate(&self, file_path: &str) {
+        self.inner.invalidate(file_path);
+    }
+
+    /// 全エントリを無効化
+    pub fn invalidate_all(&self) {
+        self.inner.invalidate_all();
+    }
+
+    /// もはや存在しないファイルのエントリを取り除く
+    pub fn evict_missing(&self, existing_paths: &HashSet<String>) {
+        let stale: Vec<String> = self
+            .inner
+            .iter()
+            .map(|(key, _)| key.as_ref().clone())
+            .filter(|key| !existing_paths.contains(key))
+            .collect();
+        for key in stale {
+            self.inner.invalidate(&key);
+        }
+    }
+}
@@ -1,11 +1,13 @@
 //! Tauriコマンド定義モジュール
 
-use crate::parser::{parse_markdown, task_to_markdown, SubTask, Task};
+use crate::cache::TaskCache;
+use crate::parser::{parse_markdown, render_markdown, task_to_markdown, SubTask, Task};
+use crate::vcs::Repo;
 use chrono::Local;
+use git2::{BranchType, Repository};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 use tauri::AppHandle;
 
 /// Git同期結果
@@ -17,15 +19,29 @@ pub struct SyncResult {
     pub message: String,
 }
 
+/// Gitステータスの詳細情報（ahead/behind や作業ツリーの状態）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatus {
+    pub branch: String,
+    pub ahead: u32,
+    pub behind: u32,
+    pub staged: u32,
+    pub modified: u32,
+    pub untracked: u32,
+    pub conflicted: u32,
+}
+
 /// 指定フォルダ内のすべてのマークダウンファイルを読み込みタスクリストを返す
+/// 変更のないファイルは `TaskCache` の内容を再利用し、再パースを省く
 #[tauri::command]
-pub fn get_tasks(folder_path: String) -> Result<Vec<Task>, String> {
+pub fn get_tasks(folder_path: String, cache: tauri::State<TaskCache>) -> Result<Vec<Task>, String> {
     let path = Path::new(&folder_path);
     if !path.exists() || !path.is_dir() {
         return Err("指定されたフォルダが存在しません".to_string());
     }
 
     let mut tasks = Vec::new();
+    let mut current_paths = std::collections::HashSet::new();
 
     let entries = fs::read_dir(path).map_err(|e| e.to_string())?;
 
@@ -40,25 +56,52 @@ pub fn get_tasks(folder_path: String) -> Result<Vec<Task>, String> {
                 continue;
             }
 
+            let file_path_str = file_path.to_string_lossy().to_string();
+            current_paths.insert(file_path_str.clone());
+
+            let mtime = fs::metadata(&file_path)
+                .and_then(|m| m.modified())
+                .map_err(|e| e.to_string())?;
+
+            if let Some(task) = cache.get_if_fresh(&file_path_str, mtime) {
+                tasks.push(task);
+                continue;
+            }
+
             let content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
-            match parse_markdown(&content, file_path.to_string_lossy().as_ref()) {
-                Ok(task) => tasks.push(task),
+            match parse_markdown(&content, &file_path_str) {
+                Ok(task) => {
+                    cache.insert(file_path_str, mtime, task.clone());
+                    tasks.push(task);
+                }
                 Err(e) => eprintln!("Failed to parse {}: {}", file_path.display(), e),
             }
         }
     }
 
+    cache.evict_missing(&current_paths);
+
     Ok(tasks)
 }
 
 /// タスクを保存（既存ファイルを上書き）
 #[tauri::command]
-pub fn save_task(task: Task) -> Result<(), String> {
+pub fn save_task(task: Task, cache: tauri::State<TaskCache>) -> Result<(), String> {
     let markdown = task_to_markdown(&task);
     fs::write(&task.file_path, markdown).map_err(|e| e.to_string())?;
+    cache.invalidate(&task.file_path);
     Ok(())
 }
 
+/// キャッシュを無効化する。`file_path` を指定すればそのファイルのみ、省略すれば全体を無効化する
+#[tauri::command]
+pub fn invalidate_cache(file_path: Option<String>, cache: tauri::State<TaskCache>) {
+    match file_path {
+        Some(file_path) => cache.invalidate(&file_path),
+        None => cache.invalidate_all(),
+    }
+}
+
 /// 新規タスクを作成
 /// フロントから渡された値で初期状態を反映し、1回の書き込みで保存する
 #[tauri::command]
@@ -71,6 +114,8 @@ pub fn create_task(
     assignee: Option<String>,
     sub_tasks: Option<Vec<SubTask>>,
     memo: Option<String>,
+    tags: Option<Vec<String>>,
+    cache: tauri::State<TaskCache>,
 ) -> Result<Task, String> {
     let now = Local::now();
     let datetime_str = now.format("%Y-%m-%d-%H:%M").to_string();
@@ -108,18 +153,27 @@ pub fn create_task(
         assignee: assignee.unwrap_or_else(|| "-".to_string()),
         sub_tasks: sub_tasks.unwrap_or_default(),
         memo: memo.unwrap_or_default(),
+        tags: tags.unwrap_or_default(),
     };
 
     let markdown = task_to_markdown(&task);
     fs::write(&file_path, markdown).map_err(|e| e.to_string())?;
+    cache.invalidate(&task.file_path);
 
     Ok(task)
 }
 
+/// タスクのメモをサニタイズ済みHTMLにレンダリング（読み取り専用プレビュー用）
+#[tauri::command]
+pub fn render_task_memo(memo: String) -> String {
+    render_markdown(&memo)
+}
+
 /// タスクを削除
 #[tauri::command]
-pub fn delete_task(file_path: String) -> Result<(), String> {
+pub fn delete_task(file_path: String, cache: tauri::State<TaskCache>) -> Result<(), String> {
     fs::remove_file(&file_path).map_err(|e| e.to_string())?;
+    cache.invalidate(&file_path);
     Ok(())
 }
 
@@ -168,190 +222,276 @@ pub fn create_task_folder(
     Ok(new_folder_path.to_string_lossy().to_string())
 }
 
-/// フォルダがGitリポジトリかどうかをチェック
+/// フォルダがGitリポジトリ（または対応VCSのリポジトリ）かどうかをチェック
 #[tauri::command]
 pub fn check_git_repo(folder_path: String) -> bool {
-    let path = Path::new(&folder_path);
+    let repo = Repo::new(folder_path);
+    repo.backend.is_repo(&repo.path)
+}
 
-    // フォルダ自体に.gitがあるか、親ディレクトリを辿って.gitを探す
-    let mut current = path;
-    loop {
-        if current.join(".git").exists() {
-            return true;
+/// Gitステータス（ahead/behind、作業ツリーの状態）を取得
+/// starshipのようなプロンプトが示す指標を同期を走らせずに得る。
+/// `git` をPATHに要求しないよう、他のGitコマンド同様git2で直接読み取る。
+#[tauri::command]
+pub fn get_git_status(folder_path: String) -> Result<GitStatus, String> {
+    let repo = Repository::discover(&folder_path).map_err(|e| format!("リポジトリを開けません: {}", e))?;
+
+    let head = repo.head().ok();
+    let branch = head
+        .as_ref()
+        .and_then(|h| h.shorthand())
+        .unwrap_or("(detached)")
+        .to_string();
+
+    let (ahead, behind) = head
+        .as_ref()
+        .and_then(|h| {
+            let local_oid = h.target()?;
+            let branch_name = h.shorthand()?;
+            let local_branch = repo.find_branch(branch_name, BranchType::Local).ok()?;
+            let upstream_oid = local_branch.upstream().ok()?.get().target()?;
+            repo.graph_ahead_behind(local_oid, upstream_oid).ok()
+        })
+        .map(|(ahead, behind)| (ahead as u32, behind as u32))
+        .unwrap_or((0, 0));
+
+    let mut status_options = git2::StatusOptions::new();
+    status_options.include_untracked(true);
+    let statuses = repo
+        .statuses(Some(&mut status_options))
+        .map_err(|e| format!("git status 失敗: {}", e))?;
+
+    let mut staged = 0;
+    let mut modified = 0;
+    let mut untracked = 0;
+    let mut conflicted = 0;
+
+    for entry in statuses.iter() {
+        let flags = entry.status();
+        if flags.is_conflicted() {
+            conflicted += 1;
+            continue;
+        }
+        if flags.intersects(
+            git2::Status::INDEX_NEW
+                | git2::Status::INDEX_MODIFIED
+                | git2::Status::INDEX_DELETED
+                | git2::Status::INDEX_RENAMED
+                | git2::Status::INDEX_TYPECHANGE,
+        ) {
+            staged += 1;
         }
-        match current.parent() {
-            Some(parent) => current = parent,
-            None => break,
+        if flags.intersects(
+            git2::Status::WT_MODIFIED
+                | git2::Status::WT_DELETED
+                | git2::Status::WT_RENAMED
+                | git2::Status::WT_TYPECHANGE,
+        ) {
+            modified += 1;
+        }
+        if flags.contains(git2::Status::WT_NEW) {
+            untracked += 1;
         }
     }
-    false
+
+    Ok(GitStatus {
+        branch,
+        ahead,
+        behind,
+        staged,
+        modified,
+        untracked,
+        conflicted,
+    })
 }
 
-/// Gitブランチ一覧を取得
+/// ブランチ一覧を取得
 #[tauri::command]
 pub fn get_git_branches(folder_path: String) -> Result<Vec<String>, String> {
-    let output = Command::new("git")
-        .args(["branch", "-a"])
-        .current_dir(&folder_path)
-        .output()
-        .map_err(|e| format!("git branch 実行エラー: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("git branch 失敗: {}", stderr));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let branches: Vec<String> = stdout
-        .lines()
-        .map(|line| {
-            line.trim()
-                .trim_start_matches("* ")
-                .trim_start_matches("remotes/origin/")
-                .to_string()
-        })
-        .filter(|b| !b.is_empty() && !b.contains("HEAD"))
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
-
-    Ok(branches)
+    let repo = Repo::new(folder_path);
+    repo.backend.branches(&repo.path)
 }
 
-/// 現在のGitブランチを取得
+/// 現在のブランチを取得
 #[tauri::command]
 pub fn get_current_branch(folder_path: String) -> Result<String, String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .current_dir(&folder_path)
-        .output()
-        .map_err(|e| format!("git rev-parse 実行エラー: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("git rev-parse 失敗: {}", stderr));
-    }
-
-    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    Ok(branch)
+    let repo = Repo::new(folder_path);
+    repo.backend.current_branch(&repo.path)
 }
 
-/// Git同期を実行（pull → add → commit → push）
+/// VCS同期を実行（Tauriコマンド本体はバックエンド選択に委譲する）
 #[tauri::command]
 pub fn git_sync(folder_path: String, branch: String) -> Result<SyncResult, String> {
-    let now = Local::now();
-    let commit_message = format!("タスク同期: {}", now.format("%Y-%m-%d %H:%M"));
-
-    // 1. 現在のブランチを確認（インライン実行で高速化）
-    let branch_output = Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .current_dir(&folder_path)
-        .output()
-        .map_err(|e| format!("git rev-parse 実行エラー: {}", e))?;
-
-    let current_branch = if branch_output.status.success() {
-        String::from_utf8_lossy(&branch_output.stdout).trim().to_string()
-    } else {
-        String::new()
-    };
+    let repo = Repo::new(folder_path);
+    repo.backend.sync(&repo.path, &branch)
+}
 
-    // 2. 必要に応じてブランチを切り替え
-    if !current_branch.is_empty() && current_branch != branch {
-        let checkout_output = Command::new("git")
-            .args(["checkout", &branch])
-            .current_dir(&folder_path)
-            .output()
-            .map_err(|e| format!("git checkout 実行エラー: {}", e))?;
-
-        if !checkout_output.status.success() {
-            let stderr = String::from_utf8_lossy(&checkout_output.stderr);
-            return Err(format!("git checkout 失敗: {}", stderr));
+/// fetch/pushで使う認証コールバックを構築する
+/// SSH鍵はまずssh-agentを試し、HTTPS等のユーザー/パスワード認証は
+/// ユーザーのgit credential helper（`git config credential.helper`）に委ねる。
+/// これにより `git pull`/`git push` がそのまま使えていた認証方式を踏襲する。
+fn build_remote_callbacks() -> git2::RemoteCallbacks<'static> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(|url, username_from_url, allowed_types| {
+        if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+            if let Some(username) = username_from_url {
+                if let Ok(cred) = git2::Cred::ssh_key_from_agent(username) {
+                    return Ok(cred);
+                }
+            }
         }
-    }
+        if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            if let Ok(config) = git2::Config::open_default() {
+                if let Ok(cred) = git2::Cred::credential_helper(&config, url, username_from_url) {
+                    return Ok(cred);
+                }
+            }
+        }
+        git2::Cred::default()
+    });
+    callbacks
+}
 
-    // 3. git fetch origin（バックグラウンドでなく必要な分だけ取得）
-    let _ = Command::new("git")
-        .args(["fetch", "origin", &branch])
-        .current_dir(&folder_path)
-        .output();
+/// Gitバックエンドによる同期の実処理（fetch → merge(fast-forward優先) → add → commit → push）
+pub(crate) fn git_sync_with_repo(folder_path: &Path, branch: &str) -> Result<SyncResult, String> {
+    let repo = Repository::discover(folder_path).map_err(|e| format!("リポジトリを開けません: {}", e))?;
 
-    // 4. git pull origin <branch>
-    let mut pulled = false;
-    let pull_output = Command::new("git")
-        .args(["pull", "origin", &branch])
-        .current_dir(&folder_path)
-        .output()
-        .map_err(|e| format!("git pull 実行エラー: {}", e))?;
-
-    if !pull_output.status.success() {
-        let stderr = String::from_utf8_lossy(&pull_output.stderr);
-        // コンフリクトの可能性をチェック
-        if stderr.contains("CONFLICT") || stderr.contains("conflict") {
-            return Ok(SyncResult {
-                pulled: false,
-                pushed: false,
-                conflicts: true,
-                message: "コンフリクトが発生しました。手動で解決してください。".to_string(),
-            });
-        }
-        // リモートブランチが存在しない場合は続行
-        if !stderr.contains("couldn't find remote ref") {
-            return Err(format!("git pull 失敗: {}", stderr));
-        }
-    } else {
-        let stdout = String::from_utf8_lossy(&pull_output.stdout);
-        pulled = !stdout.contains("Already up to date");
+    // 1. 必要に応じてブランチを切り替え
+    let current_branch = repo
+        .head()
+        .ok()
+        .and_then(|h| h.shorthand().map(|s| s.to_string()))
+        .unwrap_or_default();
+
+    if !current_branch.is_empty() && current_branch != branch {
+        let refname = format!("refs/heads/{}", branch);
+        let obj = repo
+            .revparse_single(&refname)
+            .map_err(|e| format!("git checkout 失敗: ブランチ '{}' が見つかりません ({})", branch, e))?;
+        repo.checkout_tree(&obj, None)
+            .map_err(|e| format!("git checkout 失敗: {}", e))?;
+        repo.set_head(&refname)
+            .map_err(|e| format!("git checkout 失敗: {}", e))?;
     }
 
-    // 5. git add .
-    let add_output = Command::new("git")
-        .args(["add", "."])
-        .current_dir(&folder_path)
-        .output()
-        .map_err(|e| format!("git add 実行エラー: {}", e))?;
+    // 2. git fetch origin
+    let mut remote = repo
+        .find_remote("origin")
+        .map_err(|e| format!("リモート 'origin' が見つかりません: {}", e))?;
+    let mut pulled = false;
 
-    if !add_output.status.success() {
-        let stderr = String::from_utf8_lossy(&add_output.stderr);
-        return Err(format!("git add 失敗: {}", stderr));
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(build_remote_callbacks());
+
+    if remote.fetch(&[branch], Some(&mut fetch_options), None).is_ok() {
+        if let Ok(fetch_head) = repo.find_reference("FETCH_HEAD") {
+            let fetch_commit = repo
+                .reference_to_annotated_commit(&fetch_head)
+                .map_err(|e| e.to_string())?;
+            let (analysis, _) = repo
+                .merge_analysis(&[&fetch_commit])
+                .map_err(|e| e.to_string())?;
+
+            if analysis.is_fast_forward() {
+                // 3a. fast-forwardできる場合はブランチの参照を進めるだけ
+                // 作業ツリーはsafeチェックアウトで更新し、未コミットのローカル変更は残す
+                let refname = format!("refs/heads/{}", branch);
+                let fetch_obj = repo
+                    .find_object(fetch_commit.id(), None)
+                    .map_err(|e| format!("git pull 失敗: {}", e))?;
+                repo.checkout_tree(&fetch_obj, Some(git2::build::CheckoutBuilder::default().safe()))
+                    .map_err(|e| format!("git checkout 失敗: {}", e))?;
+                let mut reference = repo
+                    .find_reference(&refname)
+                    .map_err(|e| format!("git pull 失敗: {}", e))?;
+                reference
+                    .set_target(fetch_commit.id(), "fast-forward (local-md-kanban sync)")
+                    .map_err(|e| format!("git pull 失敗: {}", e))?;
+                repo.set_head(&refname).map_err(|e| e.to_string())?;
+                pulled = true;
+            } else if analysis.is_normal() {
+                // 3b. 通常マージを試み、コンフリクトが発生したら手動解決に委ねる
+                repo.merge(&[&fetch_commit], None, None)
+                    .map_err(|e| format!("git pull 失敗: {}", e))?;
+
+                let mut index = repo.index().map_err(|e| e.to_string())?;
+                if index.has_conflicts() {
+                    return Ok(SyncResult {
+                        pulled: false,
+                        pushed: false,
+                        conflicts: true,
+                        message: "コンフリクトが発生しました。手動で解決してください。".to_string(),
+                    });
+                }
+
+                let tree_id = index.write_tree().map_err(|e| e.to_string())?;
+                let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+                let sig = repo.signature().map_err(|e| e.to_string())?;
+                let head_commit = repo
+                    .head()
+                    .map_err(|e| e.to_string())?
+                    .peel_to_commit()
+                    .map_err(|e| e.to_string())?;
+                let fetch_commit_obj = repo.find_commit(fetch_commit.id()).map_err(|e| e.to_string())?;
+                repo.commit(
+                    Some("HEAD"),
+                    &sig,
+                    &sig,
+                    &format!("Merge remote-tracking branch 'origin/{}'", branch),
+                    &tree,
+                    &[&head_commit, &fetch_commit_obj],
+                )
+                .map_err(|e| e.to_string())?;
+                repo.cleanup_state().map_err(|e| e.to_string())?;
+                pulled = true;
+            }
+        }
     }
 
-    // 6. 変更があるかチェック
-    let status_output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .current_dir(&folder_path)
-        .output()
-        .map_err(|e| format!("git status 実行エラー: {}", e))?;
+    // 4. 変更をステージ
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    index
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .map_err(|e| format!("git add 失敗: {}", e))?;
+    index.write().map_err(|e| format!("git add 失敗: {}", e))?;
+
+    if index.has_conflicts() {
+        return Ok(SyncResult {
+            pulled,
+            pushed: false,
+            conflicts: true,
+            message: "コンフリクトが発生しました。手動で解決してください。".to_string(),
+        });
+    }
 
-    let has_changes = !String::from_utf8_lossy(&status_output.stdout).trim().is_empty();
+    // 5. 変更があればコミットしてプッシュ
+    let tree_id = index.write_tree().map_err(|e| e.to_string())?;
+    let head_tree_id = repo.head().ok().and_then(|h| h.peel_to_tree().ok()).map(|t| t.id());
+    let has_changes = head_tree_id != Some(tree_id);
 
     let mut pushed = false;
     if has_changes {
-        // 7. git commit
-        let commit_output = Command::new("git")
-            .args(["commit", "-m", &commit_message])
-            .current_dir(&folder_path)
-            .output()
-            .map_err(|e| format!("git commit 実行エラー: {}", e))?;
-
-        if !commit_output.status.success() {
-            let stderr = String::from_utf8_lossy(&commit_output.stderr);
-            // "nothing to commit" は成功として扱う
-            if !stderr.contains("nothing to commit") {
-                return Err(format!("git commit 失敗: {}", stderr));
-            }
-        }
-
-        // 8. git push origin <branch>
-        let push_output = Command::new("git")
-            .args(["push", "origin", &branch])
-            .current_dir(&folder_path)
-            .output()
-            .map_err(|e| format!("git push 実行エラー: {}", e))?;
-
-        if !push_output.status.success() {
-            let stderr = String::from_utf8_lossy(&push_output.stderr);
-            return Err(format!("git push 失敗: {}", stderr));
-        }
+        let now = Local::now();
+        let commit_message = format!("タスク同期: {}", now.format("%Y-%m-%d %H:%M"));
+        let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+        let sig = repo.signature().map_err(|e| e.to_string())?;
+        let parent = repo
+            .head()
+            .map_err(|e| e.to_string())?
+            .peel_to_commit()
+            .map_err(|e| e.to_string())?;
+        repo.commit(Some("HEAD"), &sig, &sig, &commit_message, &tree, &[&parent])
+            .map_err(|e| format!("git commit 失敗: {}", e))?;
+
+        let mut push_options = git2::PushOptions::new();
+        push_options.remote_callbacks(build_remote_callbacks());
+
+        remote
+            .push(
+                &[&format!("refs/heads/{}:refs/heads/{}", branch, branch)],
+                Some(&mut push_options),
+            )
+            .map_err(|e| format!("git push 失敗: {}", e))?;
         pushed = true;
     }
 
@@ -1,8 +1,12 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod cache;
 mod commands;
 mod parser;
+mod vcs;
+
+use cache::TaskCache;
 
 fn main() {
     let new_task = tauri::CustomMenuItem::new("new_task", "新規タスク")
@@ -14,6 +18,7 @@ fn main() {
         ));
 
     tauri::Builder::default()
+        .manage(TaskCache::new())
         .menu(menu)
         .on_menu_event(|event| {
             if event.menu_item_id() == "new_task" {
@@ -23,10 +28,13 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             commands::get_tasks,
             commands::save_task,
+            commands::render_task_memo,
             commands::create_task,
             commands::delete_task,
+            commands::invalidate_cache,
             commands::create_task_folder,
             commands::check_git_repo,
+            commands::get_git_status,
             commands::get_git_branches,
             commands::get_current_branch,
             commands::git_sync,
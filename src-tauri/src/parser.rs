@@ -1,7 +1,13 @@
 //! マークダウンファイルのパースと生成を行うモジュール
 
+use comrak::adapters::SyntaxHighlighterAdapter;
+use comrak::{markdown_to_html_with_plugins, ComrakOptions, ComrakPlugins};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use syntect::html::{ClassedHTMLGenerator, ClassStyle};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
 
 /// サブタスクの構造体
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,26 +30,102 @@ pub struct Task {
     pub assignee: String,
     pub sub_tasks: Vec<SubTask>,
     pub memo: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// `---` フェンスで囲まれたYAMLフロントマターが保持しうるメタデータ
+/// リスト形式では表現できない`tags`のような構造化データのためのもの
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Frontmatter {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    created: Option<String>,
+    #[serde(default)]
+    updated: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    assignee: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// 先頭の`---`フェンスYAMLフロントマターを抽出する
+/// フロントマターが無い、またはパースに失敗した場合は元のコンテンツをそのまま返す
+fn extract_frontmatter(content: &str) -> (Option<Frontmatter>, &str) {
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return (None, content);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return (None, content);
+    };
+
+    let yaml = &rest[..end];
+    let after = &rest[end + "\n---".len()..];
+    let after = after.strip_prefix('\n').unwrap_or(after);
+
+    match serde_yaml::from_str::<Frontmatter>(yaml) {
+        Ok(frontmatter) => (Some(frontmatter), after),
+        Err(_) => (None, content),
+    }
 }
 
 /// マークダウンファイルをパースしてTaskに変換
+/// 先頭にYAMLフロントマターがあればそれを優先し、無い項目は従来のリスト形式から補う
 pub fn parse_markdown(content: &str, file_path: &str) -> Result<Task, String> {
-    let lines: Vec<&str> = content.lines().collect();
+    let (frontmatter, body) = extract_frontmatter(content);
+    let lines: Vec<&str> = body.lines().collect();
 
-    // タイトルを取得（最初の # で始まる行）
-    let title = lines
-        .iter()
-        .find(|line| line.starts_with("# "))
-        .map(|line| line.trim_start_matches("# ").to_string())
+    // タイトルを取得（フロントマター優先、無ければ最初の # で始まる行）
+    let title = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.title.clone())
+        .or_else(|| {
+            lines
+                .iter()
+                .find(|line| line.starts_with("# "))
+                .map(|line| line.trim_start_matches("# ").to_string())
+        })
         .unwrap_or_else(|| "-".to_string());
 
-    // メタデータをパース
-    let created = extract_metadata(&lines, "created").unwrap_or_else(|| "-".to_string());
-    let updated = extract_metadata(&lines, "updated").unwrap_or_else(|| "-".to_string());
-    let status = extract_metadata(&lines, "status").unwrap_or_else(|| "未着手".to_string());
-    let priority = extract_metadata(&lines, "priority").unwrap_or_else(|| "低".to_string());
-    let due = extract_metadata(&lines, "due").unwrap_or_else(|| "-".to_string());
-    let assignee = extract_metadata(&lines, "assignee").unwrap_or_else(|| "-".to_string());
+    // メタデータをパース（フロントマター優先、無ければリスト形式にフォールバック）
+    let created = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.created.clone())
+        .or_else(|| extract_metadata(&lines, "created"))
+        .unwrap_or_else(|| "-".to_string());
+    let updated = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.updated.clone())
+        .or_else(|| extract_metadata(&lines, "updated"))
+        .unwrap_or_else(|| "-".to_string());
+    let status = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.status.clone())
+        .or_else(|| extract_metadata(&lines, "status"))
+        .unwrap_or_else(|| "未着手".to_string());
+    let priority = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.priority.clone())
+        .or_else(|| extract_metadata(&lines, "priority"))
+        .unwrap_or_else(|| "低".to_string());
+    let due = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.due.clone())
+        .or_else(|| extract_metadata(&lines, "due"))
+        .unwrap_or_else(|| "-".to_string());
+    let assignee = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.assignee.clone())
+        .or_else(|| extract_metadata(&lines, "assignee"))
+        .unwrap_or_else(|| "-".to_string());
+    let tags = frontmatter.as_ref().map(|fm| fm.tags.clone()).unwrap_or_default();
 
     // サブタスクをパース
     let sub_tasks = parse_subtasks(&lines);
@@ -62,6 +144,7 @@ pub fn parse_markdown(content: &str, file_path: &str) -> Result<Task, String> {
         assignee,
         sub_tasks,
         memo,
+        tags,
     })
 }
 
@@ -132,23 +215,47 @@ fn parse_memo(lines: &[&str]) -> String {
 }
 
 /// TaskをマークダウンにΑれする
+/// `tags`のようにリスト形式では表せないフィールドを持つ場合は、
+/// 先頭にYAMLフロントマターを付与する（他のマークダウンツールとの相互運用のため）。
+/// フロントマターを出力する場合はそちらを正として、重複する`## メタデータ`のリストは書かない。
 pub fn task_to_markdown(task: &Task) -> String {
     let mut lines = Vec::new();
+    let has_frontmatter = !task.tags.is_empty();
+
+    if has_frontmatter {
+        let frontmatter = Frontmatter {
+            title: Some(task.title.clone()),
+            created: Some(task.created.clone()),
+            updated: Some(task.updated.clone()),
+            status: Some(task.status.clone()),
+            priority: Some(task.priority.clone()),
+            due: Some(task.due.clone()),
+            assignee: Some(task.assignee.clone()),
+            tags: task.tags.clone(),
+        };
+        lines.push("---".to_string());
+        let yaml = serde_yaml::to_string(&frontmatter).unwrap_or_default();
+        lines.push(yaml.trim_end().to_string());
+        lines.push("---".to_string());
+        lines.push(String::new());
+    }
 
     // タイトル
     lines.push(format!("# {}", task.title));
     lines.push(String::new());
 
-    // メタデータ
-    lines.push("## メタデータ".to_string());
-    lines.push(String::new());
-    lines.push(format!("- created: {}", task.created));
-    lines.push(format!("- updated: {}", task.updated));
-    lines.push(format!("- status: {}", task.status));
-    lines.push(format!("- priority: {}", task.priority));
-    lines.push(format!("- due: {}", task.due));
-    lines.push(format!("- assignee: {}", task.assignee));
-    lines.push(String::new());
+    if !has_frontmatter {
+        // メタデータ（フロントマター出力時はそちらが正のため省略する）
+        lines.push("## メタデータ".to_string());
+        lines.push(String::new());
+        lines.push(format!("- created: {}", task.created));
+        lines.push(format!("- updated: {}", task.updated));
+        lines.push(format!("- status: {}", task.status));
+        lines.push(format!("- priority: {}", task.priority));
+        lines.push(format!("- due: {}", task.due));
+        lines.push(format!("- assignee: {}", task.assignee));
+        lines.push(String::new());
+    }
 
     // サブタスク
     lines.push("## サブタスク".to_string());
@@ -175,3 +282,67 @@ pub fn task_to_markdown(task: &Task) -> String {
 
     lines.join("\n")
 }
+
+/// syntectでコードブロックをクラス付きHTMLにハイライトするcomrakアダプタ
+struct SyntectAdapter {
+    syntax_set: SyntaxSet,
+}
+
+impl SyntaxHighlighterAdapter for SyntectAdapter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn std::io::Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> std::io::Result<()> {
+        let syntax = lang
+            .and_then(|lang| self.syntax_set.find_syntax_by_token(lang))
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, &self.syntax_set, ClassStyle::Spaced);
+        for line in LinesWithEndings::from(code) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        }
+        write!(output, "{}", generator.finalize())
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn std::io::Write,
+        _attributes: HashMap<String, String>,
+    ) -> std::io::Result<()> {
+        write!(output, "<pre class=\"code-block\">")
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn std::io::Write,
+        attributes: HashMap<String, String>,
+    ) -> std::io::Result<()> {
+        let class = attributes.get("class").cloned().unwrap_or_default();
+        write!(output, "<code class=\"{}\">", class)
+    }
+}
+
+/// メモ等のマークダウンをサニタイズ済みHTMLに変換する
+/// GFM拡張（タスクリスト、テーブル、取り消し線、オートリンク）を有効にし、
+/// フェンスコードブロックはsyntectでシンタックスハイライトしてspanを埋め込む
+pub fn render_markdown(content: &str) -> String {
+    let mut options = ComrakOptions::default();
+    options.extension.table = true;
+    options.extension.strikethrough = true;
+    options.extension.tasklist = true;
+    options.extension.autolink = true;
+    options.render.escape = true;
+
+    let adapter = SyntectAdapter {
+        syntax_set: SyntaxSet::load_defaults_newlines(),
+    };
+    let mut plugins = ComrakPlugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+
+    markdown_to_html_with_plugins(content, &options, &plugins)
+}
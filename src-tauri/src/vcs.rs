@@ -0,0 +1,84 @@
+//! VCSバックエンドの抽象化モジュール
+//!
+//! `commands` モジュールのGit同期/ブランチ操作はすべてGitを前提に書かれていたが、
+//! それらを `Backend` トレイトの背後に隠し、将来Mercurial等の別VCSを
+//! 追加してもTauriコマンドのシグネチャを変えずに済むようにする。
+
+use crate::commands::SyncResult;
+use git2::{BranchType, Repository};
+use std::path::{Path, PathBuf};
+
+/// VCSバックエンドが実装すべき操作
+pub trait Backend {
+    /// 指定パスがこのバックエンドのリポジトリかどうか
+    fn is_repo(&self, path: &Path) -> bool;
+    /// 現在のブランチ名を取得
+    fn current_branch(&self, path: &Path) -> Result<String, String>;
+    /// ブランチ一覧（ローカル/リモート）を取得
+    fn branches(&self, path: &Path) -> Result<Vec<String>, String>;
+    /// 同期（fetch/merge/push相当）を実行
+    fn sync(&self, path: &Path, branch: &str) -> Result<SyncResult, String>;
+}
+
+/// Gitバックエンド（git2ベース）
+pub struct Git;
+
+impl Backend for Git {
+    fn is_repo(&self, path: &Path) -> bool {
+        Repository::discover(path).is_ok()
+    }
+
+    fn current_branch(&self, path: &Path) -> Result<String, String> {
+        let repo = Repository::discover(path).map_err(|e| format!("リポジトリを開けません: {}", e))?;
+        let head = repo.head().map_err(|e| format!("git rev-parse 失敗: {}", e))?;
+        Ok(head.shorthand().unwrap_or("HEAD").to_string())
+    }
+
+    fn branches(&self, path: &Path) -> Result<Vec<String>, String> {
+        let repo = Repository::discover(path).map_err(|e| format!("リポジトリを開けません: {}", e))?;
+
+        let mut branches = std::collections::HashSet::new();
+        for branch_type in [BranchType::Local, BranchType::Remote] {
+            let iter = repo
+                .branches(Some(branch_type))
+                .map_err(|e| format!("git branch 実行エラー: {}", e))?;
+            for item in iter {
+                let (branch, _) = item.map_err(|e| e.to_string())?;
+                if let Some(name) = branch.name().map_err(|e| e.to_string())? {
+                    let name = name.trim_start_matches("origin/");
+                    if !name.is_empty() && !name.contains("HEAD") {
+                        branches.insert(name.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(branches.into_iter().collect())
+    }
+
+    fn sync(&self, path: &Path, branch: &str) -> Result<SyncResult, String> {
+        crate::commands::git_sync_with_repo(path, branch)
+    }
+}
+
+/// リポジトリとそれを操作するバックエンドをまとめた型
+pub struct Repo {
+    pub backend: Box<dyn Backend>,
+    pub path: PathBuf,
+}
+
+impl Repo {
+    /// フォルダの内容からバックエンドを判定してRepoを構築する
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let backend = detect_backend(&path);
+        Self { backend, path }
+    }
+}
+
+/// フォルダの内容から使用すべきバックエンドを判定する
+/// Mercurialバックエンドが実装されるまでは `.hg` かどうかに関わらずGitを返す
+pub fn detect_backend(_path: &Path) -> Box<dyn Backend> {
+    // TODO: Mercurialバックエンドを実装したら `.git`/`.hg` で分岐させる
+    Box::new(Git)
+}